@@ -0,0 +1,70 @@
+use crate::full;
+use bytes::Bytes;
+use http_body_util::combinators::BoxBody;
+use hyper::{Response, StatusCode};
+use std::fmt;
+
+/// Unified error type for everything that can go wrong while handling a
+/// request. Each variant knows how to render itself as a JSON error
+/// response, so handlers can propagate with `?` instead of panicking.
+#[derive(Debug)]
+pub enum AppError {
+    BadJson(serde_json::Error),
+    BodyRead(hyper::Error),
+    NotFound,
+    Validation(validator::ValidationErrors),
+    Internal(String),
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::BadJson(err) => write!(f, "malformed JSON body: {}", err),
+            AppError::BodyRead(err) => write!(f, "failed to read request body: {}", err),
+            AppError::NotFound => write!(f, "not found"),
+            AppError::Validation(err) => write!(f, "validation error: {}", err),
+            AppError::Internal(msg) => write!(f, "internal error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<serde_json::Error> for AppError {
+    fn from(err: serde_json::Error) -> Self {
+        AppError::BadJson(err)
+    }
+}
+
+impl From<hyper::Error> for AppError {
+    fn from(err: hyper::Error) -> Self {
+        AppError::BodyRead(err)
+    }
+}
+
+impl AppError {
+    fn status(&self) -> StatusCode {
+        match self {
+            AppError::BadJson(_) => StatusCode::BAD_REQUEST,
+            AppError::BodyRead(_) => StatusCode::BAD_REQUEST,
+            AppError::NotFound => StatusCode::NOT_FOUND,
+            AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Renders the error as a `{"error": "..."}` JSON envelope with the
+    /// matching status code.
+    pub fn into_response(self) -> Response<BoxBody<Bytes, hyper::Error>> {
+        let status = self.status();
+        let body = serde_json::json!({ "error": self.to_string() }).to_string();
+
+        let mut response = Response::new(full(body));
+        *response.status_mut() = status;
+        response.headers_mut().insert(
+            hyper::header::CONTENT_TYPE,
+            hyper::header::HeaderValue::from_static("application/json"),
+        );
+        response
+    }
+}
@@ -1,121 +1,215 @@
 use bytes::Bytes;
 use http_body_util::{combinators::BoxBody, BodyExt, Empty, Full};
-use hyper::server::conn::http1;
 use hyper::service::service_fn;
 use hyper::{Method, Request, Response, StatusCode};
-use hyper_util::rt::TokioIo;
-use serde::{Deserialize, Serialize};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
 use std::net::SocketAddr;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use tokio::net::TcpListener;
 use validator::Validate;
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
-struct Todo {
-    #[validate(length(min = 1, max = 100))]
-    title: String,
-    completed: bool,
-}
+mod error;
+mod metrics;
+mod range;
+mod shutdown;
+mod store;
+mod tls;
+mod todo;
 
-type TodoList = Arc<Mutex<Vec<Todo>>>;
+use error::AppError;
+use hyper_util::server::graceful::GracefulShutdown;
+use metrics::Metrics;
+use store::{InMemoryStore, Store, TodoStore};
+use tls::TlsConfig;
+use todo::{Id, Todo};
 
 async fn todo_handler(
     req: Request<hyper::body::Incoming>,
-    todos: TodoList,
+    store: Store,
+    metrics: Arc<Metrics>,
 ) -> Result<Response<BoxBody<Bytes, hyper::Error>>, hyper::Error> {
-    match (req.method(), req.uri().path()) {
-        (&Method::POST, "/todos") => create_todo(req, todos).await,
-        (&Method::GET, "/todos") => list_todos(todos),
-        (&Method::PUT, "/todos") => update_todo(req, todos).await,
-        (&Method::DELETE, "/todos") => delete_todo(req, todos).await,
-        _ => Ok(error_response(
-            StatusCode::NOT_FOUND,
-            "Not Found".to_string(),
-        )),
+    let method = req.method().to_string();
+    let route = route_template(req.uri().path());
+    let started = metrics.observe_request(&method, route);
+
+    let result: Result<Response<BoxBody<Bytes, hyper::Error>>, AppError> =
+        match (req.method(), req.uri().path()) {
+            (&Method::POST, "/todos") => create_todo(req, &store, &metrics).await,
+            (&Method::GET, "/todos") => list_todos(&req, &store).await,
+            // `/todos/export` is the same representation as `/todos`; the
+            // separate path exists so clients that want a stable "give me
+            // everything" address (paired with `Range`) don't have to treat
+            // the collection route as meaning something different.
+            (&Method::GET, "/todos/export") => list_todos(&req, &store).await,
+            (&Method::GET, "/metrics") => Ok(metrics::render(&metrics.registry)),
+            (method, path) if path.starts_with("/todos/") => {
+                match path["/todos/".len()..].parse::<Id>() {
+                    Ok(id) => match *method {
+                        Method::GET => get_todo(id, &store).await,
+                        Method::PUT => update_todo(req, id, &store).await,
+                        Method::DELETE => delete_todo(id, &store, &metrics).await,
+                        _ => Err(AppError::NotFound),
+                    },
+                    Err(_) => Err(AppError::NotFound),
+                }
+            }
+            _ => Err(AppError::NotFound),
+        };
+
+    metrics.observe_latency(started);
+    Ok(result.unwrap_or_else(AppError::into_response))
+}
+
+/// Collapses a concrete request path to the route template it matches, so
+/// metrics labels stay bounded regardless of how many distinct ids are ever
+/// requested (an id per path would otherwise mint a permanent Prometheus
+/// time series for every todo that's ever existed).
+fn route_template(path: &str) -> &'static str {
+    match path {
+        "/todos" => "/todos",
+        "/todos/export" => "/todos/export",
+        "/metrics" => "/metrics",
+        path if path.starts_with("/todos/") => "/todos/{id}",
+        _ => "unmatched",
     }
 }
 
 async fn create_todo(
     req: Request<hyper::body::Incoming>,
-    todos: TodoList,
-) -> Result<Response<BoxBody<Bytes, hyper::Error>>, hyper::Error> {
+    store: &dyn TodoStore,
+    metrics: &Metrics,
+) -> Result<Response<BoxBody<Bytes, hyper::Error>>, AppError> {
     let todo: Todo = parse_json(req).await?;
-    if let Err(errors) = todo.validate() {
-        return Ok(error_response(
-            StatusCode::BAD_REQUEST,
-            format!("Validation error: {:?}", errors),
-        ));
-    }
-    todos.lock().unwrap().push(todo);
-    json_response(&todos.lock().unwrap().last().unwrap())
+    todo.validate().map_err(AppError::Validation)?;
+    let created = store.create(todo).await?;
+    metrics.todo_count.set(store.count().await? as i64);
+    Ok(json_response(&created))
 }
 
-fn list_todos(todos: TodoList) -> Result<Response<BoxBody<Bytes, hyper::Error>>, hyper::Error> {
-    json_response(&*todos.lock().unwrap())
+async fn get_todo(
+    id: Id,
+    store: &dyn TodoStore,
+) -> Result<Response<BoxBody<Bytes, hyper::Error>>, AppError> {
+    store
+        .get(id)
+        .await?
+        .map(|todo| json_response(&todo))
+        .ok_or(AppError::NotFound)
+}
+
+/// Lists the todo collection, honouring `Range` so large exports can be
+/// resumed rather than re-downloaded from scratch. Also serves
+/// `/todos/export`.
+async fn list_todos(
+    req: &Request<hyper::body::Incoming>,
+    store: &dyn TodoStore,
+) -> Result<Response<BoxBody<Bytes, hyper::Error>>, AppError> {
+    ranged_json_response(&store.list().await?, req)
+}
+
+/// Serializes `data` to JSON and, if the request carries a satisfiable
+/// `Range: bytes=start-end` header, returns only that byte slice as a `206
+/// Partial Content` response. A `Range` that's well-formed but out of bounds
+/// gets `416 Range Not Satisfiable` rather than a silent full body; a
+/// missing or unparseable header gets the full body as `200`. All three
+/// cases advertise `Accept-Ranges: bytes`.
+fn ranged_json_response<T: serde::Serialize>(
+    data: &T,
+    req: &Request<hyper::body::Incoming>,
+) -> Result<Response<BoxBody<Bytes, hyper::Error>>, AppError> {
+    let json = Bytes::from(serde_json::to_string(data).unwrap());
+    let total_len = json.len();
+
+    let range_header = req
+        .headers()
+        .get(hyper::header::RANGE)
+        .and_then(|v| v.to_str().ok());
+
+    let mut response = match range_header.and_then(|h| range::parse_bytes_range(h, total_len)) {
+        Some(range::RangeOutcome::Satisfiable(start, end)) => {
+            // Byte offsets, not char offsets: slice the raw bytes so a range
+            // boundary can never land mid-multibyte-character and panic.
+            let slice = json.slice(start..=end);
+            let mut response = Response::new(full(slice));
+            *response.status_mut() = StatusCode::PARTIAL_CONTENT;
+            response.headers_mut().insert(
+                hyper::header::CONTENT_RANGE,
+                format!("bytes {}-{}/{}", start, end, total_len)
+                    .parse()
+                    .unwrap(),
+            );
+            response
+        }
+        Some(range::RangeOutcome::Unsatisfiable) => {
+            let mut response = Response::new(empty());
+            *response.status_mut() = StatusCode::RANGE_NOT_SATISFIABLE;
+            response.headers_mut().insert(
+                hyper::header::CONTENT_RANGE,
+                format!("bytes */{}", total_len).parse().unwrap(),
+            );
+            response.headers_mut().insert(
+                hyper::header::ACCEPT_RANGES,
+                hyper::header::HeaderValue::from_static("bytes"),
+            );
+            return Ok(response);
+        }
+        None => Response::new(full(json)),
+    };
+
+    response.headers_mut().insert(
+        hyper::header::CONTENT_TYPE,
+        hyper::header::HeaderValue::from_static("application/json"),
+    );
+    response.headers_mut().insert(
+        hyper::header::ACCEPT_RANGES,
+        hyper::header::HeaderValue::from_static("bytes"),
+    );
+    Ok(response)
 }
 
 async fn update_todo(
     req: Request<hyper::body::Incoming>,
-    todos: TodoList,
-) -> Result<Response<BoxBody<Bytes, hyper::Error>>, hyper::Error> {
+    id: Id,
+    store: &dyn TodoStore,
+) -> Result<Response<BoxBody<Bytes, hyper::Error>>, AppError> {
     let todo: Todo = parse_json(req).await?;
-    if let Err(errors) = todo.validate() {
-        return Ok(error_response(
-            StatusCode::BAD_REQUEST,
-            format!("Validation error: {:?}", errors),
-        ));
-    }
-    let mut todos = todos.lock().unwrap();
-    if let Some(existing_todo) = todos.iter_mut().find(|t| t.title == todo.title) {
-        *existing_todo = todo;
-        json_response(existing_todo)
-    } else {
-        Ok(error_response(
-            StatusCode::NOT_FOUND,
-            "Todo not found".to_string(),
-        ))
-    }
+    todo.validate().map_err(AppError::Validation)?;
+    store
+        .update(id, todo)
+        .await?
+        .map(|updated| json_response(&updated))
+        .ok_or(AppError::NotFound)
 }
 
 async fn delete_todo(
-    req: Request<hyper::body::Incoming>,
-    todos: TodoList,
-) -> Result<Response<BoxBody<Bytes, hyper::Error>>, hyper::Error> {
-    let todo: Todo = parse_json(req).await?;
-    let mut todos = todos.lock().unwrap();
-    if let Some(index) = todos.iter().position(|t| t.title == todo.title) {
-        todos.remove(index);
-        Ok(Response::new(empty()))
-    } else {
-        Ok(error_response(
-            StatusCode::NOT_FOUND,
-            "Todo not found".to_string(),
-        ))
+    id: Id,
+    store: &dyn TodoStore,
+    metrics: &Metrics,
+) -> Result<Response<BoxBody<Bytes, hyper::Error>>, AppError> {
+    match store.delete(id).await? {
+        Some(_) => {
+            metrics.todo_count.set(store.count().await? as i64);
+            Ok(Response::new(empty()))
+        }
+        None => Err(AppError::NotFound),
     }
 }
 
 async fn parse_json<T: serde::de::DeserializeOwned>(
     req: Request<hyper::body::Incoming>,
-) -> Result<T, hyper::Error> {
+) -> Result<T, AppError> {
     let body_bytes = req.collect().await?.to_bytes();
-    Ok(serde_json::from_slice(&body_bytes).unwrap())
+    Ok(serde_json::from_slice(&body_bytes)?)
 }
 
-fn json_response<T: serde::Serialize>(
-    data: &T,
-) -> Result<Response<BoxBody<Bytes, hyper::Error>>, hyper::Error> {
+fn json_response<T: serde::Serialize>(data: &T) -> Response<BoxBody<Bytes, hyper::Error>> {
     let json = serde_json::to_string(data).unwrap();
     let mut response = Response::new(full(json));
     response.headers_mut().insert(
         hyper::header::CONTENT_TYPE,
         hyper::header::HeaderValue::from_static("application/json"),
     );
-    Ok(response)
-}
-
-fn error_response(status: StatusCode, message: String) -> Response<BoxBody<Bytes, hyper::Error>> {
-    let mut response = Response::new(full(message));
-    *response.status_mut() = status;
     response
 }
 
@@ -125,7 +219,7 @@ fn empty() -> BoxBody<Bytes, hyper::Error> {
         .boxed()
 }
 
-fn full<T: Into<Bytes>>(chunk: T) -> BoxBody<Bytes, hyper::Error> {
+pub(crate) fn full<T: Into<Bytes>>(chunk: T) -> BoxBody<Bytes, hyper::Error> {
     Full::new(chunk.into())
         .map_err(|never| match never {})
         .boxed()
@@ -135,23 +229,84 @@ fn full<T: Into<Bytes>>(chunk: T) -> BoxBody<Bytes, hyper::Error> {
 async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let addr = SocketAddr::from(([127, 0, 0, 1], 3100));
     let listener = TcpListener::bind(addr).await?;
-    println!("Listening on http://{}", addr);
 
-    let todos: TodoList = Arc::new(Mutex::new(Vec::new()));
+    let store: Store = match std::env::var("TODO_STORE_PATH") {
+        Ok(path) => {
+            println!("Persisting todos to {}", path);
+            Arc::new(store::FileStore::open(path).await?)
+        }
+        Err(_) => Arc::new(InMemoryStore::default()),
+    };
+    let metrics = Arc::new(Metrics::new());
+    metrics.todo_count.set(store.count().await? as i64);
+
+    let tls_acceptor = match TlsConfig::from_env() {
+        Some(config) => {
+            println!("Listening on https://{}", addr);
+            Some(config.build_acceptor()?)
+        }
+        None => {
+            println!("Listening on http://{}", addr);
+            None
+        }
+    };
+
+    let graceful = GracefulShutdown::new();
+    let shutdown_signal = shutdown::signal();
+    tokio::pin!(shutdown_signal);
 
     loop {
-        let (stream, _) = listener.accept().await?;
-        let todos = todos.clone();
+        let stream = tokio::select! {
+            accepted = listener.accept() => accepted?.0,
+            _ = &mut shutdown_signal => {
+                println!("Shutdown signal received, no longer accepting new connections");
+                break;
+            }
+        };
+
+        let store = store.clone();
+        let metrics = metrics.clone();
+        let tls_acceptor = tls_acceptor.clone();
+        let graceful = graceful.clone();
+
+        // The TLS handshake (and everything after it) happens inside the
+        // spawned task, not here, so one slow or stalled handshake can't
+        // block the accept loop from taking the next connection.
         tokio::task::spawn(async move {
-            if let Err(err) = http1::Builder::new()
-                .serve_connection(
-                    TokioIo::new(stream),
-                    service_fn(|req| todo_handler(req, todos.clone())),
-                )
-                .await
-            {
-                eprintln!("Error serving connection: {:?}", err);
+            let service = service_fn(move |req| todo_handler(req, store.clone(), metrics.clone()));
+
+            match tls_acceptor {
+                Some(acceptor) => {
+                    let stream = match acceptor.accept(stream).await {
+                        Ok(stream) => stream,
+                        Err(err) => {
+                            eprintln!("TLS handshake failed: {:?}", err);
+                            return;
+                        }
+                    };
+                    let conn = auto::Builder::new(TokioExecutor::new())
+                        .serve_connection(TokioIo::new(stream), service);
+                    if let Err(err) = graceful.watch(conn).await {
+                        eprintln!("Error serving connection: {:?}", err);
+                    }
+                }
+                None => {
+                    let conn = auto::Builder::new(TokioExecutor::new())
+                        .serve_connection(TokioIo::new(stream), service);
+                    if let Err(err) = graceful.watch(conn).await {
+                        eprintln!("Error serving connection: {:?}", err);
+                    }
+                }
             }
         });
     }
+
+    let grace_period = shutdown::grace_period();
+    println!("Waiting up to {:?} for in-flight connections to finish", grace_period);
+    tokio::select! {
+        _ = graceful.shutdown() => println!("All connections closed cleanly"),
+        _ = tokio::time::sleep(grace_period) => println!("Grace period expired, exiting"),
+    }
+
+    Ok(())
 }
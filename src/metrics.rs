@@ -0,0 +1,82 @@
+use bytes::Bytes;
+use http_body_util::combinators::BoxBody;
+use hyper::{Response, StatusCode};
+use prometheus::{Encoder, Histogram, HistogramOpts, IntCounterVec, IntGauge, Opts, Registry, TextEncoder};
+use std::time::Instant;
+
+use crate::full;
+
+/// Holds the Prometheus registry and the metrics handlers update on every request.
+pub struct Metrics {
+    pub registry: Registry,
+    pub todo_count: IntGauge,
+    pub requests_total: IntCounterVec,
+    pub handler_latency: Histogram,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+
+        let todo_count = IntGauge::new("todos_current", "Current number of todos").unwrap();
+        registry.register(Box::new(todo_count.clone())).unwrap();
+
+        let requests_total = IntCounterVec::new(
+            Opts::new("http_requests_total", "Total HTTP requests handled"),
+            &["method", "path"],
+        )
+        .unwrap();
+        registry
+            .register(Box::new(requests_total.clone()))
+            .unwrap();
+
+        let handler_latency = Histogram::with_opts(HistogramOpts::new(
+            "handler_latency_seconds",
+            "Latency of request handlers in seconds",
+        ))
+        .unwrap();
+        registry
+            .register(Box::new(handler_latency.clone()))
+            .unwrap();
+
+        Self {
+            registry,
+            todo_count,
+            requests_total,
+            handler_latency,
+        }
+    }
+
+    /// Records that a request came in and returns the start time for latency tracking.
+    pub fn observe_request(&self, method: &str, path: &str) -> Instant {
+        self.requests_total.with_label_values(&[method, path]).inc();
+        Instant::now()
+    }
+
+    pub fn observe_latency(&self, started: Instant) {
+        self.handler_latency
+            .observe(started.elapsed().as_secs_f64());
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Renders the registry in the Prometheus text exposition format.
+pub fn render(registry: &Registry) -> Response<BoxBody<Bytes, hyper::Error>> {
+    let metric_families = registry.gather();
+    let encoder = TextEncoder::new();
+    let mut buffer = Vec::new();
+    encoder.encode(&metric_families, &mut buffer).unwrap();
+
+    let mut response = Response::new(full(buffer));
+    response.headers_mut().insert(
+        hyper::header::CONTENT_TYPE,
+        hyper::header::HeaderValue::from_static("text/plain; version=0.0.4"),
+    );
+    *response.status_mut() = StatusCode::OK;
+    response
+}
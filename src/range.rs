@@ -0,0 +1,129 @@
+/// The result of parsing a `Range: bytes=start-end` header against a body of
+/// a known total length.
+#[derive(Debug, PartialEq, Eq)]
+pub enum RangeOutcome {
+    /// The range is well-formed and falls within the body; serve a `206`
+    /// for these byte offsets (inclusive).
+    Satisfiable(usize, usize),
+    /// The range is well-formed but doesn't fit the body (e.g. starts past
+    /// the end); the caller should respond `416 Range Not Satisfiable`
+    /// rather than silently serving the whole body.
+    Unsatisfiable,
+}
+
+/// Parses a single-range `Range: bytes=start-end` header value against a
+/// body of the given total length.
+///
+/// Only the single-range form is supported (no multipart ranges), which is
+/// all `list_todos`/`/todos/export` need. Returns `None` when the header is
+/// absent or malformed, in which case callers should ignore it and serve the
+/// whole body, per the usual "a Range we can't parse is advisory only" HTTP
+/// semantics; a well-formed but out-of-bounds range is `Some(Unsatisfiable)`.
+pub fn parse_bytes_range(header: &str, total_len: usize) -> Option<RangeOutcome> {
+    let spec = header.strip_prefix("bytes=")?;
+    let (start, end) = spec.split_once('-')?;
+
+    if start.is_empty() {
+        // "bytes=-500" means the last 500 bytes, with no separate end offset.
+        let suffix_len: usize = end.parse().ok()?;
+        if suffix_len == 0 || total_len == 0 {
+            return Some(RangeOutcome::Unsatisfiable);
+        }
+        let start = total_len.saturating_sub(suffix_len);
+        return Some(RangeOutcome::Satisfiable(start, total_len - 1));
+    }
+
+    let start: usize = start.parse().ok()?;
+    let end = if end.is_empty() {
+        None
+    } else {
+        Some(end.parse::<usize>().ok()?)
+    };
+
+    if total_len == 0 {
+        return Some(RangeOutcome::Unsatisfiable);
+    }
+    let end = end.map_or(total_len - 1, |end| end.min(total_len - 1));
+
+    if start > end || start >= total_len {
+        return Some(RangeOutcome::Unsatisfiable);
+    }
+
+    Some(RangeOutcome::Satisfiable(start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explicit_start_and_end() {
+        assert_eq!(
+            parse_bytes_range("bytes=0-499", 1000),
+            Some(RangeOutcome::Satisfiable(0, 499))
+        );
+        assert_eq!(
+            parse_bytes_range("bytes=500-999", 1000),
+            Some(RangeOutcome::Satisfiable(500, 999))
+        );
+    }
+
+    #[test]
+    fn open_ended_clamps_to_total_len() {
+        assert_eq!(
+            parse_bytes_range("bytes=500-", 1000),
+            Some(RangeOutcome::Satisfiable(500, 999))
+        );
+        assert_eq!(
+            parse_bytes_range("bytes=0-999999", 1000),
+            Some(RangeOutcome::Satisfiable(0, 999))
+        );
+    }
+
+    #[test]
+    fn suffix_range_returns_last_n_bytes_to_the_end() {
+        assert_eq!(
+            parse_bytes_range("bytes=-500", 1000),
+            Some(RangeOutcome::Satisfiable(500, 999))
+        );
+        assert_eq!(
+            parse_bytes_range("bytes=-10", 1000),
+            Some(RangeOutcome::Satisfiable(990, 999))
+        );
+    }
+
+    #[test]
+    fn suffix_longer_than_body_returns_whole_body() {
+        assert_eq!(
+            parse_bytes_range("bytes=-5000", 1000),
+            Some(RangeOutcome::Satisfiable(0, 999))
+        );
+    }
+
+    #[test]
+    fn unsatisfiable_ranges_are_distinguished_from_malformed() {
+        assert_eq!(
+            parse_bytes_range("bytes=1000-1001", 1000),
+            Some(RangeOutcome::Unsatisfiable)
+        );
+        assert_eq!(
+            parse_bytes_range("bytes=500-100", 1000),
+            Some(RangeOutcome::Unsatisfiable)
+        );
+        assert_eq!(
+            parse_bytes_range("bytes=-0", 1000),
+            Some(RangeOutcome::Unsatisfiable)
+        );
+        assert_eq!(
+            parse_bytes_range("bytes=0-499", 0),
+            Some(RangeOutcome::Unsatisfiable)
+        );
+    }
+
+    #[test]
+    fn malformed_headers_return_none() {
+        assert_eq!(parse_bytes_range("items=0-499", 1000), None);
+        assert_eq!(parse_bytes_range("bytes=abc-499", 1000), None);
+        assert_eq!(parse_bytes_range("bytes=0", 1000), None);
+    }
+}
@@ -0,0 +1,35 @@
+use tokio::signal::unix::{signal as unix_signal, SignalKind};
+
+/// Resolves once either Ctrl-C or SIGTERM is received, whichever comes first.
+///
+/// Used to trigger the accept loop's graceful shutdown so in-flight
+/// connections get a chance to finish instead of being dropped mid-request.
+pub async fn signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl-C handler");
+    };
+
+    let terminate = async {
+        unix_signal(SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+/// How long the server waits for in-flight connections to finish after a
+/// shutdown signal, before giving up. Configurable via `TODO_SHUTDOWN_GRACE_SECS`.
+pub fn grace_period() -> std::time::Duration {
+    let secs = std::env::var("TODO_SHUTDOWN_GRACE_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10);
+    std::time::Duration::from_secs(secs)
+}
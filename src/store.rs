@@ -0,0 +1,128 @@
+use crate::error::AppError;
+use crate::todo::{Id, Todo, Todos};
+use async_trait::async_trait;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Storage abstraction for todos, so the handlers don't care whether data
+/// lives in memory or on disk. `todo_handler` is threaded an `Arc<dyn
+/// TodoStore>` the same way it used to be threaded a `TodoList`.
+#[async_trait]
+pub trait TodoStore: Send + Sync {
+    async fn create(&self, todo: Todo) -> Result<Todo, AppError>;
+    async fn list(&self) -> Result<Vec<Todo>, AppError>;
+    async fn get(&self, id: Id) -> Result<Option<Todo>, AppError>;
+    async fn update(&self, id: Id, todo: Todo) -> Result<Option<Todo>, AppError>;
+    async fn delete(&self, id: Id) -> Result<Option<Todo>, AppError>;
+    async fn count(&self) -> Result<usize, AppError>;
+}
+
+pub type Store = Arc<dyn TodoStore>;
+
+fn lock(todos: &Mutex<Todos>) -> Result<MutexGuard<'_, Todos>, AppError> {
+    todos
+        .lock()
+        .map_err(|_| AppError::Internal("todos lock poisoned".to_string()))
+}
+
+/// Keeps todos in memory only; contents are lost on restart.
+#[derive(Default)]
+pub struct InMemoryStore {
+    todos: Mutex<Todos>,
+}
+
+#[async_trait]
+impl TodoStore for InMemoryStore {
+    async fn create(&self, todo: Todo) -> Result<Todo, AppError> {
+        Ok(lock(&self.todos)?.insert(todo))
+    }
+
+    async fn list(&self) -> Result<Vec<Todo>, AppError> {
+        Ok(lock(&self.todos)?.values().cloned().collect())
+    }
+
+    async fn get(&self, id: Id) -> Result<Option<Todo>, AppError> {
+        Ok(lock(&self.todos)?.get(id).cloned())
+    }
+
+    async fn update(&self, id: Id, todo: Todo) -> Result<Option<Todo>, AppError> {
+        Ok(lock(&self.todos)?.update(id, todo))
+    }
+
+    async fn delete(&self, id: Id) -> Result<Option<Todo>, AppError> {
+        Ok(lock(&self.todos)?.remove(id))
+    }
+
+    async fn count(&self) -> Result<usize, AppError> {
+        Ok(lock(&self.todos)?.len())
+    }
+}
+
+/// Keeps the same in-memory collection as `InMemoryStore`, but writes the
+/// whole collection to a JSON file after every mutation and reloads it on
+/// startup, so todos survive restarts.
+pub struct FileStore {
+    todos: Mutex<Todos>,
+    path: PathBuf,
+}
+
+impl FileStore {
+    pub async fn open(path: impl Into<PathBuf>) -> Result<Self, AppError> {
+        let path = path.into();
+        let todos = match tokio::fs::read(&path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|err| {
+                AppError::Internal(format!("corrupt store file {:?}: {}", path, err))
+            })?,
+            Err(_) => Todos::default(),
+        };
+        Ok(Self {
+            todos: Mutex::new(todos),
+            path,
+        })
+    }
+
+    async fn persist(&self) -> Result<(), AppError> {
+        let snapshot = serde_json::to_vec(&*lock(&self.todos)?)
+            .map_err(|err| AppError::Internal(format!("failed to serialize todos: {}", err)))?;
+        tokio::fs::write(&self.path, snapshot)
+            .await
+            .map_err(|err| AppError::Internal(format!("failed to write {:?}: {}", self.path, err)))
+    }
+}
+
+#[async_trait]
+impl TodoStore for FileStore {
+    async fn create(&self, todo: Todo) -> Result<Todo, AppError> {
+        let created = lock(&self.todos)?.insert(todo);
+        self.persist().await?;
+        Ok(created)
+    }
+
+    async fn list(&self) -> Result<Vec<Todo>, AppError> {
+        Ok(lock(&self.todos)?.values().cloned().collect())
+    }
+
+    async fn get(&self, id: Id) -> Result<Option<Todo>, AppError> {
+        Ok(lock(&self.todos)?.get(id).cloned())
+    }
+
+    async fn update(&self, id: Id, todo: Todo) -> Result<Option<Todo>, AppError> {
+        let updated = lock(&self.todos)?.update(id, todo);
+        if updated.is_some() {
+            self.persist().await?;
+        }
+        Ok(updated)
+    }
+
+    async fn delete(&self, id: Id) -> Result<Option<Todo>, AppError> {
+        let removed = lock(&self.todos)?.remove(id);
+        if removed.is_some() {
+            self.persist().await?;
+        }
+        Ok(removed)
+    }
+
+    async fn count(&self) -> Result<usize, AppError> {
+        Ok(lock(&self.todos)?.len())
+    }
+}
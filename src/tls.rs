@@ -0,0 +1,57 @@
+use rustls_pemfile::{certs, private_key};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+use tokio_rustls::TlsAcceptor;
+
+/// Paths to a PEM cert chain and private key, read from the environment.
+///
+/// Set `TODO_TLS_CERT` and `TODO_TLS_KEY` to enable HTTPS; when either is
+/// unset the server falls back to plaintext HTTP.
+pub struct TlsConfig {
+    pub cert_path: String,
+    pub key_path: String,
+}
+
+impl TlsConfig {
+    pub fn from_env() -> Option<Self> {
+        let cert_path = std::env::var("TODO_TLS_CERT").ok()?;
+        let key_path = std::env::var("TODO_TLS_KEY").ok()?;
+        Some(Self {
+            cert_path,
+            key_path,
+        })
+    }
+
+    /// Loads the configured cert/key pair and builds a `TlsAcceptor` with
+    /// ALPN advertising `h2` and `http/1.1`. The connection must then be
+    /// served with something that actually speaks both, e.g.
+    /// `hyper_util::server::conn::auto::Builder`.
+    pub fn build_acceptor(&self) -> Result<TlsAcceptor, Box<dyn std::error::Error + Send + Sync>> {
+        let cert_chain = load_certs(&self.cert_path)?;
+        let key = load_key(&self.key_path)?;
+
+        let mut config = rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(cert_chain, key)?;
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+}
+
+fn load_certs(
+    path: impl AsRef<Path>,
+) -> Result<Vec<rustls::pki_types::CertificateDer<'static>>, Box<dyn std::error::Error + Send + Sync>>
+{
+    let mut reader = BufReader::new(File::open(path)?);
+    Ok(certs(&mut reader).collect::<Result<Vec<_>, _>>()?)
+}
+
+fn load_key(
+    path: impl AsRef<Path>,
+) -> Result<rustls::pki_types::PrivateKeyDer<'static>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    private_key(&mut reader)?.ok_or_else(|| "no private key found in key file".into())
+}
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use validator::Validate;
+
+pub type Id = u64;
+
+#[derive(Debug, Serialize, Deserialize, Validate, Clone)]
+pub struct Todo {
+    #[serde(default)]
+    pub id: Id,
+    #[validate(length(min = 1, max = 100))]
+    pub title: String,
+    pub completed: bool,
+}
+
+/// A todo collection keyed by a server-assigned id so clients get a stable
+/// address for each resource instead of matching on title. Shared by every
+/// `TodoStore` implementation; stores that persist to disk serialize this
+/// directly.
+///
+/// Keyed by a `BTreeMap` rather than a `HashMap` so `values()` iterates in a
+/// deterministic, id-ascending order: `/todos/export`'s `Range` support
+/// depends on the serialized byte layout being stable across mutations and
+/// across a `FileStore` reload, which a `HashMap`'s random iteration order
+/// cannot guarantee.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Todos {
+    items: BTreeMap<Id, Todo>,
+    next_id: Id,
+}
+
+impl Todos {
+    pub fn insert(&mut self, mut todo: Todo) -> Todo {
+        self.next_id += 1;
+        todo.id = self.next_id;
+        self.items.insert(todo.id, todo.clone());
+        todo
+    }
+
+    pub fn get(&self, id: Id) -> Option<&Todo> {
+        self.items.get(&id)
+    }
+
+    pub fn update(&mut self, id: Id, mut todo: Todo) -> Option<Todo> {
+        if !self.items.contains_key(&id) {
+            return None;
+        }
+        todo.id = id;
+        self.items.insert(id, todo.clone());
+        Some(todo)
+    }
+
+    pub fn remove(&mut self, id: Id) -> Option<Todo> {
+        self.items.remove(&id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Todo> {
+        self.items.values()
+    }
+}